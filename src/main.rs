@@ -1,7 +1,9 @@
-use crate::MoonCalcMode::{RISE, SET};
-use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use crate::RiseSetMode::{RISE, SET};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
 use clap::Parser;
 use std::f32::consts::PI;
+use std::str::FromStr;
 
 use moon::moon_api_server::{MoonApi, MoonApiServer};
 use moon::{MoonInfoRequest, MoonInfoResponse};
@@ -11,9 +13,14 @@ pub mod moon {
     tonic::include_proto!("moon");
 }
 
-const ZONE_OFFSET: f64 = 9.0;
 const R: f64 = 0.585556;
 
+// 太陽の高度の目安(度): 視半径・大気差込みの出没、市民・航海・天文薄明
+const SUN_ALTITUDE_VISIBLE: f64 = -0.833;
+const SUN_ALTITUDE_CIVIL_TWILIGHT: f64 = -6.0;
+const SUN_ALTITUDE_NAUTICAL_TWILIGHT: f64 = -12.0;
+const SUN_ALTITUDE_ASTRONOMICAL_TWILIGHT: f64 = -18.0;
+
 /**
  * 座標
  */
@@ -38,7 +45,7 @@ struct Equatorial {
     latitude: f64,
 }
 
-enum MoonCalcMode {
+enum RiseSetMode {
     RISE,
     SET,
 }
@@ -57,35 +64,152 @@ impl MoonApi for MyMoonApi {
         let request = request.into_inner();
         let sec = request.date.unwrap().seconds;
         let date = Utc.timestamp(sec, 0).date().naive_utc();
-        let moon_age = get_moon_age(date);
+        let tz = parse_timezone(&request.timezone);
+        let zone_offset = zone_offset_hours(tz, date.and_hms(0, 0, 0));
+
+        let moon_age = get_moon_age(date, zone_offset);
+        let (illumination, phase) = get_moon_phase(date, zone_offset);
 
         let geocode = Geocode {
             longitude: request.longitude,
             latitude: request.latitude,
         };
 
-        let d = get_moon_rise_set(date, &geocode, RISE);
-        let moon_rise_sec = date.and_hms(0, 0, 0).timestamp() + (60.0 * 60.0 * 24.0 * d) as i64;
-        let moon_rise = Some(prost_types::Timestamp {
-            seconds: moon_rise_sec,
-            nanos: 0,
-        });
+        let d = get_moon_rise_set(date, &geocode, RISE, zone_offset);
+        let moon_rise = day_fraction_to_timestamp(date, Some(d));
+
+        let d = get_moon_rise_set(date, &geocode, SET, zone_offset);
+        let moon_set = day_fraction_to_timestamp(date, Some(d));
 
-        let d = get_moon_rise_set(date, &geocode, SET);
-        let moon_set_sec = date.and_hms(0, 0, 0).timestamp() + (60.0 * 60.0 * 24.0 * d) as i64;
-        let moon_set = Some(prost_types::Timestamp {
-            seconds: moon_set_sec,
-            nanos: 0,
-        });
+        let sun_rise = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(date, &geocode, RISE, SUN_ALTITUDE_VISIBLE, zone_offset),
+        );
+        let sun_set = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(date, &geocode, SET, SUN_ALTITUDE_VISIBLE, zone_offset),
+        );
+        let civil_twilight_begin = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(date, &geocode, RISE, SUN_ALTITUDE_CIVIL_TWILIGHT, zone_offset),
+        );
+        let civil_twilight_end = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(date, &geocode, SET, SUN_ALTITUDE_CIVIL_TWILIGHT, zone_offset),
+        );
+        let nautical_twilight_begin = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(
+                date,
+                &geocode,
+                RISE,
+                SUN_ALTITUDE_NAUTICAL_TWILIGHT,
+                zone_offset,
+            ),
+        );
+        let nautical_twilight_end = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(
+                date,
+                &geocode,
+                SET,
+                SUN_ALTITUDE_NAUTICAL_TWILIGHT,
+                zone_offset,
+            ),
+        );
+        let astronomical_twilight_begin = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(
+                date,
+                &geocode,
+                RISE,
+                SUN_ALTITUDE_ASTRONOMICAL_TWILIGHT,
+                zone_offset,
+            ),
+        );
+        let astronomical_twilight_end = day_fraction_to_timestamp(
+            date,
+            get_sun_rise_set(
+                date,
+                &geocode,
+                SET,
+                SUN_ALTITUDE_ASTRONOMICAL_TWILIGHT,
+                zone_offset,
+            ),
+        );
 
         let response = moon::MoonInfoResponse {
             moon_rise,
             moon_set,
             moon_age,
+            illumination,
+            phase: phase as i32,
+            sun_rise,
+            sun_set,
+            civil_twilight_begin,
+            civil_twilight_end,
+            nautical_twilight_begin,
+            nautical_twilight_end,
+            astronomical_twilight_begin,
+            astronomical_twilight_end,
         };
 
         Ok(Response::new(response))
     }
+
+    async fn eclipses(
+        &self,
+        request: Request<moon::EclipsesRequest>,
+    ) -> Result<Response<moon::EclipsesResponse>, Status> {
+        println!("Got a request: {:?}", request);
+
+        let request = request.into_inner();
+        let from = Utc.timestamp(request.from.unwrap().seconds, 0).naive_utc();
+        let to = Utc.timestamp(request.to.unwrap().seconds, 0).naive_utc();
+        let zone_offset = zone_offset_hours(Tz::UTC, from);
+
+        let eclipses = eclipses(from, to, zone_offset)
+            .into_iter()
+            .map(|e| moon::Eclipse {
+                instant: Some(prost_types::Timestamp {
+                    seconds: e.instant.timestamp(),
+                    nanos: 0,
+                }),
+                r#type: e.eclipse_type as i32,
+                magnitude_hint: e.magnitude_hint as i32,
+            })
+            .collect();
+
+        Ok(Response::new(moon::EclipsesResponse { eclipses }))
+    }
+}
+
+/**
+ * dateの0時からの経過日数dをタイムスタンプに変換する。
+ * dがNone(その緯度でその高度に太陽が到達しない=極域の白夜・極夜)ならNoneを返す
+ */
+fn day_fraction_to_timestamp(date: NaiveDate, d: Option<f64>) -> Option<prost_types::Timestamp> {
+    d.map(|d| prost_types::Timestamp {
+        seconds: date.and_hms(0, 0, 0).timestamp() + (60.0 * 60.0 * 24.0 * d) as i64,
+        nanos: 0,
+    })
+}
+
+/**
+ * IANAタイムゾーン名をパースする。空文字列や不正な値はUTCとして扱う
+ */
+fn parse_timezone(timezone: &str) -> Tz {
+    if timezone.is_empty() {
+        return Tz::UTC;
+    }
+    Tz::from_str(timezone).unwrap_or(Tz::UTC)
+}
+
+/**
+ * 指定日時におけるタイムゾーンのUTCからのオフセット(時間単位、DSTを考慮)
+ */
+fn zone_offset_hours(tz: Tz, datetime: NaiveDateTime) -> f64 {
+    tz.offset_from_utc_datetime(&datetime).fix().local_minus_utc() as f64 / 3600.0
 }
 
 #[derive(clap::Subcommand, Clone, Debug)]
@@ -94,6 +218,16 @@ enum Mode {
     Calc {
         #[arg(short, long)]
         date: String,
+        #[arg(long)]
+        timezone: Option<String>,
+    },
+    Phases {
+        #[arg(long)]
+        year: i32,
+    },
+    Eclipses {
+        #[arg(long)]
+        year: i32,
     },
 }
 
@@ -111,10 +245,30 @@ fn main() {
         Mode::Serve => {
             serve().unwrap();
         }
-        Mode::Calc { date } => {
-            let result = calc(NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap());
+        Mode::Calc { date, timezone } => {
+            let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap();
+            let timezone = timezone
+                .or_else(|| iana_time_zone::get_timezone().ok())
+                .unwrap_or_else(|| "UTC".to_string());
+            let result = calc(date, parse_timezone(&timezone));
             println!("{:?}", result);
         }
+        Mode::Phases { year } => {
+            for (instant, name) in phases_in_year(year) {
+                println!("{}: {}", name, instant);
+            }
+        }
+        Mode::Eclipses { year } => {
+            let from = NaiveDate::from_ymd(year, 1, 1).and_hms(0, 0, 0);
+            let to = NaiveDate::from_ymd(year + 1, 1, 1).and_hms(0, 0, 0);
+            let zone_offset = zone_offset_hours(Tz::UTC, from);
+            for event in eclipses(from, to, zone_offset) {
+                println!(
+                    "{}: {:?} ({:?})",
+                    event.instant, event.eclipse_type, event.magnitude_hint
+                );
+            }
+        }
     };
 }
 
@@ -131,15 +285,16 @@ async fn serve() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn calc(date: NaiveDate) -> f64 {
-    let moon_age = get_moon_age(date);
+fn calc(date: NaiveDate, tz: Tz) -> f64 {
+    let zone_offset = zone_offset_hours(tz, date.and_hms(0, 0, 0));
+    let moon_age = get_moon_age(date, zone_offset);
     return moon_age;
 }
 
 /**
  * 月齢の計算
  */
-fn get_moon_age(date: NaiveDate) -> f64 {
+fn get_moon_age(date: NaiveDate, zone_offset: f64) -> f64 {
     const THRESHOLD_DELTA_LAMBDA: f64 = 0.05;
     let datetime = date.and_hms(12, 0, 0);
     let t = datetime.timestamp() as f64;
@@ -147,8 +302,8 @@ fn get_moon_age(date: NaiveDate) -> f64 {
     let mut tn = t;
     let mut gn: f64;
     loop {
-        let lm = get_moon_longitude(NaiveDateTime::from_timestamp(tn as i64, 0));
-        let ls = get_sun_longitude(NaiveDateTime::from_timestamp(tn as i64, 0));
+        let lm = get_moon_longitude(NaiveDateTime::from_timestamp(tn as i64, 0), zone_offset);
+        let ls = get_sun_longitude(NaiveDateTime::from_timestamp(tn as i64, 0), zone_offset);
         // println!("lm: {}", lm);
         // println!("ls: {}", ls);
         let delta_l = lm - ls;
@@ -175,7 +330,186 @@ fn get_moon_age(date: NaiveDate) -> f64 {
     }
 }
 
-fn get_moon_rise_set(date: NaiveDate, geocode: &Geocode, mode: MoonCalcMode) -> f64 {
+/**
+ * fromに最も近い、月日黄経差(離角)がtarget_elongationとなる瞬間の計算
+ * (get_moon_ageのニュートン法を朔望以外の任意の離角に一般化したもの)
+ */
+fn get_next_phase(from: NaiveDateTime, target_elongation: f64, zone_offset: f64) -> NaiveDateTime {
+    const THRESHOLD_DELTA_LAMBDA: f64 = 0.05;
+    let t = from.timestamp() as f64;
+
+    let mut tn = t;
+    loop {
+        let lm = get_moon_longitude(NaiveDateTime::from_timestamp(tn as i64, 0), zone_offset);
+        let ls = get_sun_longitude(NaiveDateTime::from_timestamp(tn as i64, 0), zone_offset);
+        // 0/360の継ぎ目付近で発散しないよう、毎回[-180, 180]に正規化してから微分係数で割る
+        let delta_l = adjust180abs(adjust0to360(lm - ls) - target_elongation);
+
+        let gn = delta_l / 12.1908;
+        tn -= gn * 86400.0;
+
+        if delta_l.abs() < THRESHOLD_DELTA_LAMBDA {
+            return NaiveDateTime::from_timestamp(tn as i64, 0);
+        }
+    }
+}
+
+const SYNODIC_MONTH_DAYS: f64 = 29.53;
+
+/**
+ * year年の新月・上弦・満月・下弦の瞬間を一覧にする
+ * (朔望月の日数でストライドしながらget_next_phaseで各瞬間を拾い、年内のものだけ残す)
+ */
+fn phases_in_year(year: i32) -> Vec<(NaiveDateTime, &'static str)> {
+    const TARGETS: [(f64, &str); 4] = [
+        (0.0, "new moon"),
+        (90.0, "first quarter"),
+        (180.0, "full moon"),
+        (270.0, "last quarter"),
+    ];
+
+    let year_start = NaiveDate::from_ymd(year, 1, 1).and_hms(0, 0, 0);
+    let year_end = NaiveDate::from_ymd(year + 1, 1, 1).and_hms(0, 0, 0);
+    let zone_offset = zone_offset_hours(Tz::UTC, year_start);
+
+    let mut results = Vec::new();
+    let mut guess = year_start - chrono::Duration::days(SYNODIC_MONTH_DAYS as i64 + 1);
+    while guess < year_end {
+        for (target, name) in TARGETS.iter() {
+            let instant = get_next_phase(guess, *target, zone_offset);
+            if instant >= year_start && instant < year_end {
+                results.push((instant, *name));
+            }
+        }
+        guess += chrono::Duration::seconds((SYNODIC_MONTH_DAYS * 86400.0) as i64);
+    }
+
+    results.sort_by_key(|(instant, _)| *instant);
+    results.dedup_by(|a, b| a.1 == b.1 && (a.0 - b.0).num_hours().abs() < 12);
+    results
+}
+
+/**
+ * 日食・月食の候補イベント
+ */
+struct EclipseEvent {
+    instant: NaiveDateTime,
+    eclipse_type: moon::EclipseType,
+    magnitude_hint: moon::EclipseMagnitudeHint,
+}
+
+// 食限界(度): この範囲内に朔望が交点に近いと食が起こりうる
+const SOLAR_ECLIPSE_LIMIT_DEG: f64 = 16.5;
+const LUNAR_ECLIPSE_LIMIT_DEG: f64 = 11.0;
+
+/**
+ * fromからtoまでの朔(日食候補)・望(月食候補)を洗い出し、月の交点への近さで食を判定する
+ * (朔望瞬間における月の引数緯度Fが0/180度=交点付近にあるほど食限界に収まりやすい)
+ */
+fn eclipses(from: NaiveDateTime, to: NaiveDateTime, zone_offset: f64) -> Vec<EclipseEvent> {
+    const TARGETS: [(f64, moon::EclipseType); 2] = [
+        (0.0, moon::EclipseType::Solar),
+        (180.0, moon::EclipseType::Lunar),
+    ];
+
+    let mut events = Vec::new();
+    let mut guess = from - chrono::Duration::days(SYNODIC_MONTH_DAYS as i64 + 1);
+    while guess < to {
+        for (target, eclipse_type) in TARGETS.iter() {
+            let instant = get_next_phase(guess, *target, zone_offset);
+            if instant < from || instant >= to {
+                continue;
+            }
+
+            let f = moon_argument_of_latitude(instant, zone_offset);
+            let node_distance = distance_to_node(f);
+            let limit = match eclipse_type {
+                moon::EclipseType::Solar => SOLAR_ECLIPSE_LIMIT_DEG,
+                moon::EclipseType::Lunar => LUNAR_ECLIPSE_LIMIT_DEG,
+                moon::EclipseType::Unspecified => continue,
+            };
+            if node_distance > limit {
+                continue;
+            }
+
+            let magnitude_hint = if node_distance <= limit / 3.0 {
+                moon::EclipseMagnitudeHint::Total
+            } else {
+                moon::EclipseMagnitudeHint::Partial
+            };
+            events.push(EclipseEvent {
+                instant,
+                eclipse_type: *eclipse_type,
+                magnitude_hint,
+            });
+        }
+        guess += chrono::Duration::seconds((SYNODIC_MONTH_DAYS * 86400.0) as i64);
+    }
+
+    events.sort_by_key(|e| e.instant);
+    events.dedup_by(|a, b| {
+        a.eclipse_type == b.eclipse_type && (a.instant - b.instant).num_hours().abs() < 12
+    });
+    events
+}
+
+/**
+ * 月の引数緯度F(月の平均黄経L'と昇交点黄経Ωの差)の近似計算。
+ * F=0/180度で月は軌道の交点上にあり、食が起こりうる
+ */
+fn moon_argument_of_latitude(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000day(datetime, zone_offset) / 36525.0;
+
+    let omega = 125.04452 - 1934.136261 * t;
+    let lp = 218.3165 + 481267.8813 * t;
+
+    adjust0to360(lp - omega)
+}
+
+/**
+ * 引数緯度Fから最寄りの交点(0度または180度)までの距離
+ */
+fn distance_to_node(f: f64) -> f64 {
+    let m = adjust0to360(f) % 180.0;
+    m.min(180.0 - m)
+}
+
+/**
+ * 月の満ち欠け(輝面比・名称)の計算
+ */
+fn get_moon_phase(date: NaiveDate, zone_offset: f64) -> (f64, moon::MoonPhase) {
+    let datetime = date.and_hms(12, 0, 0);
+    let elongation = adjust0to360(
+        get_moon_longitude(datetime, zone_offset) - get_sun_longitude(datetime, zone_offset),
+    );
+    let illumination = (1.0 - deg2rad(elongation).cos()) / 2.0;
+
+    (illumination, moon_phase_name(elongation))
+}
+
+/**
+ * 離角から8分類の月相名を求める(0/90/180/270度を中心とした45度幅のビン)
+ */
+fn moon_phase_name(elongation: f64) -> moon::MoonPhase {
+    match elongation {
+        e if e < 22.5 => moon::MoonPhase::New,
+        e if e < 67.5 => moon::MoonPhase::WaxingCrescent,
+        e if e < 112.5 => moon::MoonPhase::FirstQuarter,
+        e if e < 157.5 => moon::MoonPhase::WaxingGibbous,
+        e if e < 202.5 => moon::MoonPhase::Full,
+        e if e < 247.5 => moon::MoonPhase::WaningGibbous,
+        e if e < 292.5 => moon::MoonPhase::LastQuarter,
+        e if e < 337.5 => moon::MoonPhase::WaningCrescent,
+        _ => moon::MoonPhase::New,
+    }
+}
+
+fn get_moon_rise_set(
+    date: NaiveDate,
+    geocode: &Geocode,
+    mode: RiseSetMode,
+    zone_offset: f64,
+) -> f64 {
     const THRESHOLD_DELTA_D: f64 = 0.000005;
 
     let mut delta_d = 0.0;
@@ -192,11 +526,11 @@ fn get_moon_rise_set(date: NaiveDate, geocode: &Geocode, mode: MoonCalcMode) ->
             )
             .naive_utc();
 
-        let moon_parallax = get_moon_parallax(tmp_datetime);
+        let moon_parallax = get_moon_parallax(tmp_datetime, zone_offset);
 
         let moon_equatorial = ecliptic2equatorial(
-            get_moon_ecliptic(tmp_datetime),
-            ecliptic_tilt_angle(datetime_hms0),
+            get_moon_ecliptic(tmp_datetime, zone_offset),
+            ecliptic_tilt_angle(datetime_hms0, zone_offset),
         );
         // println!("a: {}", moon_equatorial.longitude);
         // println!("d: {}", moon_equatorial.latitude);
@@ -212,7 +546,8 @@ fn get_moon_rise_set(date: NaiveDate, geocode: &Geocode, mode: MoonCalcMode) ->
                 SET => 1.0,
             };
         // println!("tk: {}", tk);
-        let t = get_sidereal_time(datetime_hms0) + 360.9856474 * d + geocode.longitude
+        let t = get_sidereal_time(datetime_hms0, zone_offset) + 360.9856474 * d
+            + geocode.longitude
             - moon_equatorial.longitude;
         // println!("t: {}", t);
         delta_d = adjust180abs(tk - t) / 347.8;
@@ -228,17 +563,104 @@ fn get_moon_rise_set(date: NaiveDate, geocode: &Geocode, mode: MoonCalcMode) ->
 }
 
 /**
- * UTC0時のグリニッジ恒星時
+ * 太陽の出没・薄明時刻の計算
+ * 目的の高度hをパラメータにすることで、視半径・大気差込みの出没(SUN_ALTITUDE_VISIBLE)だけでなく
+ * 市民・航海・天文薄明も同じ式で求められる。
+ * cos(t)が[-1, 1]に収まらない(その高度に到達しない=極域の白夜・極夜)場合はNoneを返す
+ */
+fn get_sun_rise_set(
+    date: NaiveDate,
+    geocode: &Geocode,
+    mode: RiseSetMode,
+    altitude: f64,
+    zone_offset: f64,
+) -> Option<f64> {
+    const THRESHOLD_DELTA_D: f64 = 0.000005;
+
+    let mut delta_d = 0.0;
+    let mut d = 0.5;
+
+    let datetime_hms0 = date.and_hms(0, 0, 0);
+
+    loop {
+        d += delta_d;
+        let tmp_datetime = Utc
+            .timestamp(
+                datetime_hms0.timestamp() + (60.0 * 60.0 * 24.0 * d) as i64,
+                0,
+            )
+            .naive_utc();
+
+        let sun_equatorial = ecliptic2equatorial(
+            Ecliptic {
+                longitude: get_sun_longitude(tmp_datetime, zone_offset),
+                latitude: 0.0,
+            },
+            ecliptic_tilt_angle(datetime_hms0, zone_offset),
+        );
+
+        let cos_tk = (deg2rad(altitude).sin()
+            - deg2rad(sun_equatorial.latitude).sin() * deg2rad(geocode.latitude).sin())
+            / (deg2rad(sun_equatorial.latitude).cos() * deg2rad(geocode.latitude).cos());
+        if !(-1.0..=1.0).contains(&cos_tk) {
+            return None;
+        }
+
+        let tk = rad2deg(cos_tk.acos())
+            * match mode {
+                RISE => -1.0,
+                SET => 1.0,
+            };
+        let t = get_sidereal_time(datetime_hms0, zone_offset) + 360.9856474 * d
+            + geocode.longitude
+            - sun_equatorial.longitude;
+        delta_d = adjust180abs(tk - t) / 347.8;
+        if delta_d.abs() < THRESHOLD_DELTA_D {
+            break;
+        }
+    }
+
+    Some(d + delta_d)
+}
+
+/**
+ * UTC0時のグリニッジ恒星時(視恒星時: 章動による赤経方向のずれ(Δψ・cosε)を加味)
  */
-fn get_sidereal_time(datetime: NaiveDateTime) -> f64 {
-    let jd = j2000year(datetime);
-    100.4606 + 360.007700536 * jd + 0.00000003879 * jd.powi(2) - 15.0 * ZONE_OFFSET
+fn get_sidereal_time(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let jd = j2000year(datetime, zone_offset);
+    let (delta_psi, _) = nutation(datetime, zone_offset);
+
+    100.4606 + 360.007700536 * jd + 0.00000003879 * jd.powi(2) - 15.0 * zone_offset
+        + delta_psi * deg2rad(mean_obliquity(jd)).cos()
+}
+
+/**
+ * 章動(Δψ: 黄経の章動, Δε: 黄道傾斜の章動)を、月の昇交点・太陽と月の平均黄経から
+ * 主要項のみで近似計算する。tはJ2000.0からのユリウス世紀数
+ */
+fn nutation(datetime: NaiveDateTime, zone_offset: f64) -> (f64, f64) {
+    let t = j2000day(datetime, zone_offset) / 36525.0;
+
+    let omega = 125.04452 - 1934.136261 * t;
+    let l = 280.4665 + 36000.7698 * t;
+    let lp = 218.3165 + 481267.8813 * t;
+
+    let delta_psi_arcsec = -17.20 * deg2rad(omega).sin()
+        - 1.32 * deg2rad(2.0 * l).sin()
+        - 0.23 * deg2rad(2.0 * lp).sin()
+        + 0.21 * deg2rad(2.0 * omega).sin();
+    let delta_eps_arcsec = 9.20 * deg2rad(omega).cos()
+        + 0.57 * deg2rad(2.0 * l).cos()
+        + 0.10 * deg2rad(2.0 * lp).cos()
+        - 0.09 * deg2rad(2.0 * omega).cos();
+
+    (delta_psi_arcsec / 3600.0, delta_eps_arcsec / 3600.0)
 }
 
 /**
  * year年month月day日0時のJ2000.0(2000年１月１日力学時正午)からの経過日数
  */
-fn j2000day(datetime: NaiveDateTime) -> f64 {
+fn j2000day(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
     let year = datetime.year();
     let month = datetime.month();
     let day = datetime.day();
@@ -255,40 +677,81 @@ fn j2000day(datetime: NaiveDateTime) -> f64 {
     }
     let t = (hour as f64 * 60.0 * 60.0 + min as f64 * 60.0 + sec as f64) / 86400.0;
 
-    // FIXME: 自転遅れの計算がこれだと2018年の69秒と一致しないので最新のトレンドで計算する必要がある
-    // 地球の自転遅れ補正
-    let rotate_rev = (57.0 + 0.8 * (year as f64 - 1990.0)) / 86400.0;
-    //let rotate_rev = 64.0 / 86400.0;
+    // 地球の自転遅れ補正(UT1/UTCと力学時TTとの差ΔT)
+    let rotate_rev = delta_t(year, month) / 86400.0;
 
-    365.0 * fixed_year + 30.0 * fixed_month + fixed_day - 33.5 - (ZONE_OFFSET as f64 / 24.0)
+    365.0 * fixed_year + 30.0 * fixed_month + fixed_day - 33.5 - (zone_offset / 24.0)
         + (3.0 * (fixed_month + 1.0) / 5.0).floor()
         + (fixed_year / 4.0).floor()
         + t
         + rotate_rev
 }
 
+/**
+ * ΔT(地球時TTと協定世界時UTCの差、単位は秒)の近似計算
+ * Espenak & Meeusの区分多項式によるもので、yはmonthの中央(month-0.5)/12を加えた小数年
+ */
+fn delta_t(year: i32, month: u32) -> f64 {
+    let y = year as f64 + (month as f64 - 0.5) / 12.0;
+
+    if (2050.0..2150.0).contains(&y) {
+        let u = (y - 1820.0) / 100.0;
+        -20.0 + 32.0 * u.powi(2) - 0.5628 * (2150.0 - y)
+    } else if (2005.0..2050.0).contains(&y) {
+        let u = y - 2000.0;
+        62.92 + 0.32217 * u + 0.005589 * u.powi(2)
+    } else if (1986.0..2005.0).contains(&y) {
+        let u = y - 2000.0;
+        63.86 + 0.3345 * u - 0.060374 * u.powi(2)
+            + 0.0017275 * u.powi(3)
+            + 0.000651814 * u.powi(4)
+            + 0.00002373599 * u.powi(5)
+    } else if (1961.0..1986.0).contains(&y) {
+        let u = y - 1975.0;
+        45.45 + 1.067 * u - u.powi(2) / 260.0 - u.powi(3) / 718.0
+    } else if (1941.0..1961.0).contains(&y) {
+        let u = y - 1950.0;
+        29.07 + 0.407 * u - u.powi(2) / 233.0 + u.powi(3) / 2547.0
+    } else if (1920.0..1941.0).contains(&y) {
+        let u = y - 1920.0;
+        21.20 + 0.84493 * u - 0.0761 * u.powi(2) + 0.0020936 * u.powi(3)
+    } else if (1900.0..1920.0).contains(&y) {
+        let u = y - 1900.0;
+        -2.79 + 1.494119 * u - 0.0598939 * u.powi(2) + 0.0061966 * u.powi(3) - 0.000197 * u.powi(4)
+    } else if (1860.0..1900.0).contains(&y) {
+        let u = y - 1860.0;
+        7.62 + 0.5737 * u - 0.251754 * u.powi(2) + 0.01680668 * u.powi(3)
+            - 0.0004473624 * u.powi(4)
+            + u.powi(5) / 233174.0
+    } else {
+        // 2150年以降は長期近似式そのもの。1860年より前は現代の区分多項式の対象外のため、同じ長期近似式で代用する
+        let u = (y - 1820.0) / 100.0;
+        -20.0 + 32.0 * u.powi(2)
+    }
+}
+
 /**
  * year年month月day日0時のJ2000.0(2000年１月１日力学時正午)からの経過年数
  */
-fn j2000year(datetime: NaiveDateTime) -> f64 {
-    j2000day(datetime) / 365.25
+fn j2000year(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    j2000day(datetime, zone_offset) / 365.25
 }
 
 /**
  * 月の黄道座標
  */
-fn get_moon_ecliptic(datetime: NaiveDateTime) -> Ecliptic {
+fn get_moon_ecliptic(datetime: NaiveDateTime, zone_offset: f64) -> Ecliptic {
     Ecliptic {
-        longitude: get_moon_longitude(datetime),
-        latitude: get_moon_latitude(datetime),
+        longitude: get_moon_longitude(datetime, zone_offset),
+        latitude: get_moon_latitude(datetime, zone_offset),
     }
 }
 
 /**
  * 月の黄経の近似計算
  */
-fn get_moon_longitude(datetime: NaiveDateTime) -> f64 {
-    let t = j2000year(datetime);
+fn get_moon_longitude(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000year(datetime, zone_offset);
     let am = 0.0040 * deg2rad(119.5 + 1.33 * t).sin()
         + 0.0020 * deg2rad(55.0 + 19.34 * t).sin()
         + 0.0006 * deg2rad(71.0 + 0.2 * t).sin()
@@ -366,8 +829,8 @@ fn get_moon_longitude(datetime: NaiveDateTime) -> f64 {
 /**
  * 月の黄緯の近似計算
  */
-fn get_moon_latitude(datetime: NaiveDateTime) -> f64 {
-    let t = j2000year(datetime);
+fn get_moon_latitude(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000year(datetime, zone_offset);
     let bm = 0.0267 * deg2rad(234.95 + 19.341 * t).sin()
         + 0.0043 * deg2rad(322.1 + 19.36 * t).sin()
         + 0.0040 * deg2rad(119.5 + 1.33 * t).sin()
@@ -428,8 +891,8 @@ fn get_moon_latitude(datetime: NaiveDateTime) -> f64 {
 /**
  * 月の視差を近似計算
  */
-fn get_moon_parallax(datetime: NaiveDateTime) -> f64 {
-    let t = j2000year(datetime);
+fn get_moon_parallax(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000year(datetime, zone_offset);
 
     let p = 0.9507 * deg2rad(90.0).sin()
         + 0.0518 * deg2rad(224.98 + 4771.989 * t).sin()
@@ -447,8 +910,8 @@ fn get_moon_parallax(datetime: NaiveDateTime) -> f64 {
 /**
  * 太陽の黄経の近似計算
  */
-fn get_sun_longitude(datetime: NaiveDateTime) -> f64 {
-    let t = j2000year(datetime);
+fn get_sun_longitude(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000year(datetime, zone_offset);
     let l = 280.4603
         + 360.00769 * t
         + (1.9146 - 0.00005 * t) * deg2rad(357.538 + 359.991 * t).sin()
@@ -498,11 +961,19 @@ fn ecliptic2equatorial(ecliptic: Ecliptic, e: f64) -> Equatorial {
 }
 
 /**
- * 黄道傾角
+ * 黄道傾角(視黄道傾斜角: 平均黄道傾斜角に章動Δεを加えたもの)
  */
-fn ecliptic_tilt_angle(datetime: NaiveDateTime) -> f64 {
-    let t = j2000year(datetime);
+fn ecliptic_tilt_angle(datetime: NaiveDateTime, zone_offset: f64) -> f64 {
+    let t = j2000year(datetime, zone_offset);
+    let (_, delta_eps) = nutation(datetime, zone_offset);
 
+    adjust0to360(mean_obliquity(t) + delta_eps)
+}
+
+/**
+ * 平均黄道傾斜角
+ */
+fn mean_obliquity(t: f64) -> f64 {
     adjust0to360(23.439291 - 0.000130042 * t)
 }
 